@@ -14,7 +14,10 @@ pub mod pallet {
 		dispatch::DispatchResult,
 		fail,
 		pallet_prelude::*,
-		traits::{Currency, Randomness, ReservableCurrency, Time},
+		sp_runtime::traits::{Saturating, Zero},
+		sp_std::vec::Vec,
+		storage::unhashed,
+		traits::{Currency, EnsureOrigin, Randomness, ReservableCurrency, Time},
 		Printable,
 	};
 	use frame_system::pallet_prelude::*;
@@ -58,16 +61,73 @@ pub mod pallet {
 		type HoldingDepositForOneKitty: Get<BalanceOf<Self>>;
 		/// Time
 		type Time: Time;
+		/// The origin which may perform administrative operations, e.g. repairing kitties.
+		type AdminOrigin: EnsureOrigin<Self::Origin>;
+		/// The maximum number of kitties exported by one `export_registry` call.
+		#[pallet::constant]
+		type MaxRegistryExportBatch: Get<u32>;
+		/// The maximum number of display clones of one kitty.
+		#[pallet::constant]
+		type MaxDisplayClonesPerKitty: Get<u32>;
+		/// Kitties in the nursery are bred every `NurseryInterval` blocks, zero disables it.
+		#[pallet::constant]
+		type NurseryInterval: Get<Self::BlockNumber>;
+		/// The maximum number of kitties born in the nursery in one interval.
+		#[pallet::constant]
+		type MaxNurseryBreedsPerInterval: Get<u32>;
+		/// The maximum number of nursery entries visited in one interval.
+		#[pallet::constant]
+		type MaxNurseryScanPerInterval: Get<u32>;
+		/// Enable the strict checks of `breed`, see its documentation for the order of checks.
+		#[pallet::constant]
+		type StrictBreeding: Get<bool>;
+		/// In strict mode, a kitty can only breed when it is at least this old.
+		#[pallet::constant]
+		type MinBreedingAge: Get<MomentOf<Self>>;
+		/// In strict mode, a kitty can only breed again after this period since it last bred.
+		#[pallet::constant]
+		type BreedingCooldown: Get<MomentOf<Self>>;
+		/// In strict mode, a kitty which became a parent this many times is sterile.
+		/// Zero means no limit.
+		#[pallet::constant]
+		type MaxBreedingTimes: Get<u32>;
+		/// In strict mode, kitties can only breed in the first `BreedingSeasonLength` of every
+		/// `BreedingSeasonPeriod`. A zero period means breeding all year round.
+		#[pallet::constant]
+		type BreedingSeasonPeriod: Get<MomentOf<Self>>;
+		/// The length of the breeding season in every `BreedingSeasonPeriod`.
+		#[pallet::constant]
+		type BreedingSeasonLength: Get<MomentOf<Self>>;
 	}
 
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
 	pub struct Pallet<T>(_);
 
+	/// The number of kitties, display clones are NOT counted.
 	#[pallet::storage]
 	#[pallet::getter(fn kitties_count)]
 	pub type KittiesCount<T> = StorageValue<_, u32>;
 
+	/// The index of the last created kitty, display clones included.
+	///
+	/// Before display clones existed it was always equal to `KittiesCount`, which is used instead
+	/// when this is not set yet.
+	#[pallet::storage]
+	#[pallet::getter(fn last_kitty_id)]
+	pub type LastKittyId<T> = StorageValue<_, u32>;
+
+	/// The number of display clones.
+	#[pallet::storage]
+	#[pallet::getter(fn display_clones_count)]
+	pub type DisplayClonesCount<T> = StorageValue<_, u32>;
+
+	/// The number of display clones of each kitty.
+	#[pallet::storage]
+	#[pallet::getter(fn display_clones_of)]
+	pub type DisplayClonesOf<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyId, u32, OptionQuery>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn kitties)]
 	pub type Kitties<T: Config> =
@@ -83,6 +143,41 @@ pub mod pallet {
 	pub type KittiesPrice<T: Config> =
 		StorageMap<_, Blake2_128Concat, T::KittyId, BalanceOf<T>, OptionQuery>;
 
+	/// Display clones, mapped to the kitty they were cloned from.
+	#[pallet::storage]
+	#[pallet::getter(fn display_clone_source)]
+	pub type DisplayClones<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyId, T::KittyId, OptionQuery>;
+
+	/// Kitties deposited to the nursery, keyed by the owner who deposited them.
+	#[pallet::storage]
+	#[pallet::getter(fn nursery)]
+	pub type Nursery<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		T::KittyId,
+		(),
+		OptionQuery,
+	>;
+
+	/// The moment each kitty last became a parent.
+	#[pallet::storage]
+	#[pallet::getter(fn kitties_last_bred)]
+	pub type KittiesLastBred<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyId, MomentOf<T>, OptionQuery>;
+
+	/// The number of times each kitty became a parent.
+	#[pallet::storage]
+	#[pallet::getter(fn kitties_bred_times)]
+	pub type KittiesBredTimes<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyId, u32, OptionQuery>;
+
+	/// The raw key of the last nursery entry visited, the next interval resumes after it.
+	#[pallet::storage]
+	pub type NurseryCursor<T> = StorageValue<_, Vec<u8>>;
+
 	#[pallet::event]
 	#[pallet::metadata(T::AccountId = "AccountId")]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
@@ -95,6 +190,20 @@ pub mod pallet {
 		KittyPriceSet(T::KittyId, BalanceOf<T>),
 		KittyPriceCleared(T::KittyId),
 		KittySold(T::KittyId, T::AccountId, T::AccountId, BalanceOf<T>),
+		KittyRepaired(T::KittyId),
+		/// A kitty in the registry: id, owner, dna and price.
+		RegistryEntry(T::KittyId, Option<T::AccountId>, [u8; 16], Option<BalanceOf<T>>),
+		/// A kitty in the registry which failed to decode: id and owner.
+		RegistryEntryCorrupted(T::KittyId, Option<T::AccountId>),
+		/// The kitty exported right before is a display clone: id and source kitty.
+		RegistryDisplayClone(T::KittyId, T::KittyId),
+		/// A page of the registry was exported, with the cursor to continue from (if any).
+		RegistryExported(Option<u32>),
+		DisplayCloneCreated(T::KittyId, T::KittyId),
+		KittyDepositedToNursery(T::KittyId, T::AccountId),
+		KittyWithdrawnFromNursery(T::KittyId, T::AccountId),
+		/// A kitty was born in the nursery: owner, new kitty and its parents.
+		NurseryBreeding(T::AccountId, T::KittyId, T::KittyId, T::KittyId),
 	}
 
 	#[pallet::error]
@@ -106,6 +215,30 @@ pub mod pallet {
 		CanNotBreedWithSameGender,
 		KittyNotForSell,
 		NoNeedToBuyKittyWithoutAnOwner,
+		KittyIsDisplayClone,
+		TooManyDisplayClones,
+		KittyCorrupted,
+		KittyNotCorrupted,
+		ExportLimitIsZero,
+		KittyAlreadyInNursery,
+		KittyNotInNursery,
+		CanNotBreedWithSelf,
+		CanNotBreedOwnerlessKitty,
+		KittyTooYoung,
+		KittyInCooldown,
+		KittySterile,
+		OutOfBreedingSeason,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			let interval = T::NurseryInterval::get();
+			if interval.is_zero() || !(n % interval).is_zero() {
+				return 0
+			}
+			Self::breed_nursery()
+		}
 	}
 
 	#[pallet::call]
@@ -136,6 +269,7 @@ pub mod pallet {
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
 			ensure!(Kitties::<T>::contains_key(id), Error::<T>::KittyNotExists);
+			Self::ensure_not_display_clone(&id)?;
 			Self::ensure_owner(&id, &who)?;
 
 			Self::transfer_kitty(&id, &who, &new_owner)?;
@@ -149,13 +283,49 @@ pub mod pallet {
 		/// The two kitties MUST have different genders.
 		/// The person who help breeding will NOT become the owner of new born kitty automatically.
 		/// The owner of new born kitty is left empty, which means it can be 'adopted'.
+		///
+		/// The breeding is checked in the following order, the first failed check decides the
+		/// error so that clients get deterministic diagnostics (`id1` is checked before `id2`):
+		///
+		/// 1. `KittyNotExists`: the kitty does not exist.
+		/// 2. `CanNotBreedWithSelf`: the two ids are the same kitty (strict mode only).
+		/// 3. `KittyIsDisplayClone`: the kitty is a display clone.
+		/// 4. `CanNotBreedOwnerlessKitty`: the kitty has no owner (strict mode only).
+		/// 5. `KittyCorrupted`: the stored kitty can NOT be decoded.
+		/// 6. `OutOfBreedingSeason`: now is out of the breeding season (strict mode only).
+		/// 7. `KittyTooYoung`: the kitty is younger than `MinBreedingAge` (strict mode only).
+		/// 8. `KittyInCooldown`: the kitty bred within `BreedingCooldown` (strict mode only).
+		/// 9. `KittySterile`: the kitty bred `MaxBreedingTimes` times (strict mode only).
+		/// 10. `CanNotBreedWithSameGender`: the two kitties have the same gender.
+		///
+		/// This order is chosen on purpose: the checks on the call itself come first, then the
+		/// time of breeding and the state of each kitty, and the genders last. Otherwise breeding
+		/// a kitty with itself, which always has the same gender, would never be reported as
+		/// `CanNotBreedWithSelf`.
+		///
+		/// Strict mode is enabled by `StrictBreeding`, the nursery skips kitties which fail the
+		/// strict checks too.
 		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
 		pub fn breed(origin: OriginFor<T>, id1: T::KittyId, id2: T::KittyId) -> DispatchResult {
 			let who = ensure_signed(origin)?;
 			ensure!(Kitties::<T>::contains_key(id1), Error::<T>::KittyNotExists);
 			ensure!(Kitties::<T>::contains_key(id2), Error::<T>::KittyNotExists);
+			let strict = T::StrictBreeding::get();
+			ensure!(!strict || id1 != id2, Error::<T>::CanNotBreedWithSelf);
+			Self::ensure_not_display_clone(&id1)?;
+			Self::ensure_not_display_clone(&id2)?;
+			if strict {
+				ensure!(
+					KittiesOwner::<T>::contains_key(id1),
+					Error::<T>::CanNotBreedOwnerlessKitty
+				);
+				ensure!(
+					KittiesOwner::<T>::contains_key(id2),
+					Error::<T>::CanNotBreedOwnerlessKitty
+				);
+			}
 
-			let id = Self::breed_kitty(&id1, &id2, &who)?;
+			let id = Self::breed_kitty(&id1, &id2, &who, strict)?;
 
 			Self::deposit_event(Event::KittyBorn(id, id1, id2));
 			Ok(())
@@ -173,6 +343,7 @@ pub mod pallet {
 			T::Currency::unreserve(&who, T::HoldingDepositForOneKitty::get());
 			KittiesOwner::<T>::remove(id);
 			KittiesPrice::<T>::remove(id);
+			Nursery::<T>::remove(&who, id);
 
 			Self::deposit_event(Event::KittyAbandoned(id.clone()));
 			Ok(())
@@ -185,6 +356,7 @@ pub mod pallet {
 		pub fn adopt(origin: OriginFor<T>, id: T::KittyId) -> DispatchResult {
 			let who = ensure_signed(origin)?;
 			ensure!(Kitties::<T>::contains_key(id), Error::<T>::KittyNotExists);
+			Self::ensure_not_display_clone(&id)?;
 			ensure!(!KittiesOwner::<T>::contains_key(id), Error::<T>::CanNotAdoptKittyWithAnOwner);
 
 			T::Currency::reserve(&who, T::HoldingDepositForOneKitty::get())?;
@@ -205,6 +377,7 @@ pub mod pallet {
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
 			ensure!(Kitties::<T>::contains_key(id), Error::<T>::KittyNotExists);
+			Self::ensure_not_display_clone(&id)?;
 			Self::ensure_owner(&id, &who)?;
 
 			KittiesPrice::<T>::insert(id, price);
@@ -235,6 +408,7 @@ pub mod pallet {
 		pub fn buy(origin: OriginFor<T>, id: T::KittyId) -> DispatchResult {
 			let buyer = ensure_signed(origin)?;
 			ensure!(Kitties::<T>::contains_key(id), Error::<T>::KittyNotExists);
+			Self::ensure_not_display_clone(&id)?;
 			let owner = match KittiesOwner::<T>::get(id) {
 				Some(owner) => owner,
 				None => fail!(Error::<T>::NoNeedToBuyKittyWithoutAnOwner),
@@ -258,9 +432,167 @@ pub mod pallet {
 			Self::deposit_event(Event::KittySold(id.clone(), owner.clone(), buyer.clone(), price));
 			Ok(())
 		}
+
+		/// Overwrite the stored data of a kitty with the given dna.
+		///
+		/// Only an entry which can no longer be decoded (e.g. after a migration) can be repaired,
+		/// and display clones can NOT be repaired.
+		/// The birth time of the repaired kitty is reset to now.
+		/// This function can only be called by `AdminOrigin`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 1))]
+		pub fn repair_kitty(origin: OriginFor<T>, id: T::KittyId, dna: [u8; 16]) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(Kitties::<T>::contains_key(id), Error::<T>::KittyNotExists);
+			Self::ensure_not_display_clone(&id)?;
+			ensure!(Self::try_decode_kitty(&id).is_none(), Error::<T>::KittyNotCorrupted);
+
+			Kitties::<T>::insert(id, Kitty { dna, birth_time: T::Time::now() });
+
+			Self::deposit_event(Event::KittyRepaired(id));
+			Ok(())
+		}
+
+		/// Export a page of the kitty registry as `RegistryEntry` events, for off-chain snapshots.
+		///
+		/// A display clone is exported like a kitty and followed by a `RegistryDisplayClone` event.
+		/// A kitty which fails to decode is exported as `RegistryEntryCorrupted`, so it is not
+		/// mistaken for a missing one.
+		/// At most `MaxRegistryExportBatch` kitties are exported, starting from the kitty with
+		/// index `start`. The `RegistryExported` event carries the `start` of the next page,
+		/// or `None` when the whole registry has been exported. `limit` can NOT be zero.
+		/// This function can only be called by `AdminOrigin`.
+		#[pallet::weight({
+			let n = (*limit).min(T::MaxRegistryExportBatch::get()) as u64;
+			10_000 + T::DbWeight::get().reads_writes(4 * n, 2 * n + 1)
+		})]
+		pub fn export_registry(origin: OriginFor<T>, start: u32, limit: u32) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(limit > 0, Error::<T>::ExportLimitIsZero);
+
+			let count = Self::last_kitty_index();
+			let start = start.max(1);
+			let end = start
+				.saturating_add(limit.min(T::MaxRegistryExportBatch::get()))
+				.min(count.saturating_add(1));
+			for index in start..end {
+				let id = T::KittyId::from(index);
+				match Self::try_decode_kitty(&id) {
+					Some(kitty) => Self::deposit_event(Event::RegistryEntry(
+						id,
+						KittiesOwner::<T>::get(id),
+						kitty.dna,
+						KittiesPrice::<T>::get(id),
+					)),
+					None if Kitties::<T>::contains_key(id) => Self::deposit_event(
+						Event::RegistryEntryCorrupted(id, KittiesOwner::<T>::get(id)),
+					),
+					None => continue,
+				}
+				if let Some(source) = DisplayClones::<T>::get(id) {
+					Self::deposit_event(Event::RegistryDisplayClone(id, source));
+				}
+			}
+
+			let next = if end <= count { Some(end) } else { None };
+			Self::deposit_event(Event::RegistryExported(next));
+			Ok(())
+		}
+
+		/// Create a display clone of a kitty for public galleries.
+		///
+		/// The clone has the same dna as the source kitty, but it never has an owner
+		/// and can NOT be adopted, transferred, bred or sold.
+		/// A kitty can have at most `MaxDisplayClonesPerKitty` display clones.
+		/// This function can only be called by the owner of the kitty.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(8, 5))]
+		pub fn create_display(origin: OriginFor<T>, id: T::KittyId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Kitties::<T>::contains_key(id), Error::<T>::KittyNotExists);
+			Self::ensure_not_display_clone(&id)?;
+			Self::ensure_owner(&id, &who)?;
+			let kitty = Self::try_decode_kitty(&id).ok_or(Error::<T>::KittyCorrupted)?;
+			let clones = Self::display_clones_of(id).unwrap_or(0);
+			ensure!(clones < T::MaxDisplayClonesPerKitty::get(), Error::<T>::TooManyDisplayClones);
+
+			let clone_id = Self::insert_kitty(kitty.dna)?;
+			DisplayClones::<T>::insert(clone_id, id);
+			DisplayClonesOf::<T>::insert(id, clones + 1);
+			DisplayClonesCount::<T>::put(Self::display_clones_count().unwrap_or(0) + 1);
+
+			Self::deposit_event(Event::DisplayCloneCreated(id, clone_id));
+			Ok(())
+		}
+
+		/// Deposit a kitty to the nursery of its owner.
+		///
+		/// Every `NurseryInterval` blocks, kitties in the nursery of the same owner are paired
+		/// and bred automatically, the new born kitties are owned by that owner.
+		/// This function can only be called by the owner of the kitty.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn deposit_to_nursery(origin: OriginFor<T>, id: T::KittyId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Kitties::<T>::contains_key(id), Error::<T>::KittyNotExists);
+			Self::ensure_owner(&id, &who)?;
+			ensure!(!Nursery::<T>::contains_key(&who, id), Error::<T>::KittyAlreadyInNursery);
+
+			Nursery::<T>::insert(&who, id, ());
+
+			Self::deposit_event(Event::KittyDepositedToNursery(id, who));
+			Ok(())
+		}
+
+		/// Withdraw a kitty from the nursery.
+		///
+		/// This function can only be called by the one who deposited the kitty.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn withdraw_from_nursery(origin: OriginFor<T>, id: T::KittyId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Nursery::<T>::contains_key(&who, id), Error::<T>::KittyNotInNursery);
+
+			Nursery::<T>::remove(&who, id);
+
+			Self::deposit_event(Event::KittyWithdrawnFromNursery(id, who));
+			Ok(())
+		}
 	}
 
 	impl<T: Config> Pallet<T> {
+		/// All kitties, in the order of their ids. Display clones are excluded.
+		///
+		/// Entries which fail to decode are skipped with a warning instead of failing the query.
+		pub fn all_kitties() -> Vec<(T::KittyId, Kitty<T>)> {
+			(1..=Self::last_kitty_index())
+				.map(T::KittyId::from)
+				.filter(|id| !DisplayClones::<T>::contains_key(id))
+				.filter_map(|id| Self::try_decode_kitty(&id).map(|kitty| (id, kitty)))
+				.collect()
+		}
+
+		/// All kitties owned by `owner`, in the order of their ids. Display clones are excluded.
+		///
+		/// Entries which fail to decode are skipped with a warning instead of failing the query.
+		pub fn kitties_of(owner: &T::AccountId) -> Vec<(T::KittyId, Kitty<T>)> {
+			Self::all_kitties()
+				.into_iter()
+				.filter(|(id, _)| KittiesOwner::<T>::get(id).as_ref() == Some(owner))
+				.collect()
+		}
+
+		fn try_decode_kitty(id: &T::KittyId) -> Option<Kitty<T>> {
+			let raw = unhashed::get_raw(&Kitties::<T>::hashed_key_for(id))?;
+			match Kitty::<T>::decode(&mut &raw[..]) {
+				Ok(kitty) => Some(kitty),
+				Err(_) => {
+					log::warn!(
+						target: "runtime::kitties",
+						"kitty {:?} failed to decode",
+						id,
+					);
+					None
+				}
+			}
+		}
+
 		fn get_random_value(sender: &T::AccountId) -> [u8; 16] {
 			let payload = (
 				T::Randomness::random_seed(),
@@ -270,21 +602,28 @@ pub mod pallet {
 			payload.using_encoded(blake2_128)
 		}
 
+		fn last_kitty_index() -> u32 {
+			Self::last_kitty_id().or_else(Self::kitties_count).unwrap_or(0)
+		}
+
 		fn get_next_kitty_id() -> Result<(T::KittyId, u32), DispatchError> {
-			let count = match Self::kitties_count() {
-				Some(count) => {
-					ensure!(count != u32::MAX, Error::<T>::KittiesCountOverflow);
-					count + 1
-				}
-				None => 1,
-			};
-			Ok((T::KittyId::from(count), count))
+			let index = Self::last_kitty_index();
+			ensure!(index != u32::MAX, Error::<T>::KittiesCountOverflow);
+			Ok((T::KittyId::from(index + 1), index + 1))
 		}
 
-		fn create_kitty(dna: [u8; 16]) -> Result<T::KittyId, DispatchError> {
-			let (id, count) = Self::get_next_kitty_id()?;
+		/// Store a new kitty without counting it, display clones are created this way.
+		fn insert_kitty(dna: [u8; 16]) -> Result<T::KittyId, DispatchError> {
+			let (id, index) = Self::get_next_kitty_id()?;
 			Kitties::<T>::insert(id, Kitty { dna, birth_time: T::Time::now() });
-			KittiesCount::<T>::put(count);
+			LastKittyId::<T>::put(index);
+
+			Ok(id)
+		}
+
+		fn create_kitty(dna: [u8; 16]) -> Result<T::KittyId, DispatchError> {
+			let id = Self::insert_kitty(dna)?;
+			KittiesCount::<T>::put(Self::kitties_count().unwrap_or(0) + 1);
 
 			Ok(id)
 		}
@@ -293,17 +632,185 @@ pub mod pallet {
 			id1: &T::KittyId,
 			id2: &T::KittyId,
 			who: &T::AccountId,
+			strict: bool,
 		) -> Result<T::KittyId, DispatchError> {
-			let kitty1 = Kitties::<T>::get(id1).unwrap();
-			let kitty2 = Kitties::<T>::get(id2).unwrap();
+			let kitty1 = Self::try_decode_kitty(id1).ok_or(Error::<T>::KittyCorrupted)?;
+			let kitty2 = Self::try_decode_kitty(id2).ok_or(Error::<T>::KittyCorrupted)?;
+			let now = T::Time::now();
+			if strict {
+				Self::ensure_ready_to_breed(&[(id1, &kitty1), (id2, &kitty2)], now)?;
+			}
 			ensure!(kitty1.gender() != kitty2.gender(), Error::<T>::CanNotBreedWithSameGender);
 
-			let selector = Self::get_random_value(&who);
+			let id = Self::create_kitty(Self::breed_dna(&kitty1, &kitty2, who))?;
+			Self::record_breeding(id1, now);
+			Self::record_breeding(id2, now);
+			Ok(id)
+		}
+
+		/// The strict checks on the time of breeding and the state of each kitty, in the order
+		/// documented by `breed`.
+		fn ensure_ready_to_breed(
+			kitties: &[(&T::KittyId, &Kitty<T>)],
+			now: MomentOf<T>,
+		) -> DispatchResult {
+			let period = T::BreedingSeasonPeriod::get();
+			ensure!(
+				period.is_zero() || now % period < T::BreedingSeasonLength::get(),
+				Error::<T>::OutOfBreedingSeason
+			);
+			for &(_, kitty) in kitties {
+				ensure!(
+					now.saturating_sub(kitty.birth_time) >= T::MinBreedingAge::get(),
+					Error::<T>::KittyTooYoung
+				);
+			}
+			for &(id, _) in kitties {
+				if let Some(last_bred) = KittiesLastBred::<T>::get(id) {
+					ensure!(
+						now.saturating_sub(last_bred) >= T::BreedingCooldown::get(),
+						Error::<T>::KittyInCooldown
+					);
+				}
+			}
+			let max_times = T::MaxBreedingTimes::get();
+			for &(id, _) in kitties {
+				ensure!(
+					max_times == 0 || Self::kitties_bred_times(id).unwrap_or(0) < max_times,
+					Error::<T>::KittySterile
+				);
+			}
+			Ok(())
+		}
+
+		fn record_breeding(id: &T::KittyId, now: MomentOf<T>) {
+			KittiesLastBred::<T>::insert(id, now);
+			KittiesBredTimes::<T>::insert(id, Self::kitties_bred_times(id).unwrap_or(0) + 1);
+		}
+
+		fn breed_dna(kitty1: &Kitty<T>, kitty2: &Kitty<T>, who: &T::AccountId) -> [u8; 16] {
+			let selector = Self::get_random_value(who);
 			let mut dna = [0u8; 16];
 			for i in 0..dna.len() {
 				dna[i] = (selector[i] & kitty1.dna[i]) | (selector[i] & kitty2.dna[i]);
 			}
-			Self::create_kitty(dna)
+			dna
+		}
+
+		/// Pair and breed the kitties in the nursery, returns the weight consumed.
+		///
+		/// A kitty is paired with a kitty of the same owner and a different gender, and each
+		/// kitty is bred at most once. At most `MaxNurseryBreedsPerInterval` kitties are born and
+		/// at most `MaxNurseryScanPerInterval` entries are visited, the scan resumes from
+		/// `NurseryCursor` in the next interval. When the scan stops in the middle of the entries
+		/// of an owner, it resumes from the first entry of that owner (unless the interval started
+		/// with that owner), so kitties on both sides of the stop can still be paired.
+		/// In strict mode, kitties which fail the strict checks of `breed` are skipped.
+		/// Kitties which are no longer owned by the one who deposited them are removed.
+		fn breed_nursery() -> Weight {
+			let max_breeds = T::MaxNurseryBreedsPerInterval::get();
+			let max_scan = T::MaxNurseryScanPerInterval::get();
+			let deposit = T::HoldingDepositForOneKitty::get();
+			let strict = T::StrictBreeding::get();
+			let now = T::Time::now();
+			let mut reads: Weight = 1;
+			let mut writes: Weight = 1;
+			let mut bred = 0u32;
+			let mut scanned = 0u32;
+			let mut stale = Vec::new();
+			// Entries of the same owner are adjacent, because the owner is the first key.
+			// So only the kitties of the current owner wait for a mate, by gender.
+			let mut current_owner = None;
+			let mut males: Vec<(T::KittyId, Kitty<T>)> = Vec::new();
+			let mut females: Vec<(T::KittyId, Kitty<T>)> = Vec::new();
+
+			let start_key = NurseryCursor::<T>::take();
+			// The key right before the current entry and right before the current owner's entries.
+			let mut last_key = start_key.clone();
+			let mut owner_start_key = start_key.clone();
+			let mut entries = match start_key.clone() {
+				Some(key) => Nursery::<T>::iter_from(key),
+				None => Nursery::<T>::iter(),
+			}
+			.peekable();
+			while scanned < max_scan && bred < max_breeds {
+				let (owner, id, _) = match entries.next() {
+					Some(entry) => entry,
+					None => break,
+				};
+				scanned += 1;
+				reads += 3;
+				if current_owner.as_ref() != Some(&owner) {
+					males.clear();
+					females.clear();
+					current_owner = Some(owner.clone());
+					owner_start_key = last_key.clone();
+				}
+				last_key = Some(Nursery::<T>::hashed_key_for(&owner, id));
+				if KittiesOwner::<T>::get(id).as_ref() != Some(&owner) {
+					stale.push((owner, id));
+					continue
+				}
+				let kitty = match Self::try_decode_kitty(&id) {
+					Some(kitty) => kitty,
+					None => continue,
+				};
+				if strict {
+					reads += 2;
+					if Self::ensure_ready_to_breed(&[(&id, &kitty)], now).is_err() {
+						continue
+					}
+				}
+				let (waiting, mates) = match kitty.gender() {
+					Gender::Male => (&mut males, &mut females),
+					Gender::Female => (&mut females, &mut males),
+				};
+				let (mate_id, mate_kitty) = match mates.pop() {
+					Some(mate) => mate,
+					None => {
+						waiting.push((id, kitty));
+						continue
+					}
+				};
+
+				reads += 2;
+				if T::Currency::reserve(&owner, deposit).is_err() {
+					continue
+				}
+				writes += 1;
+				match Self::create_kitty(Self::breed_dna(&mate_kitty, &kitty, &owner)) {
+					Ok(child) => {
+						KittiesOwner::<T>::insert(child, owner.clone());
+						Self::record_breeding(&mate_id, now);
+						Self::record_breeding(&id, now);
+						writes += 8;
+						bred += 1;
+						Self::deposit_event(Event::NurseryBreeding(owner, child, mate_id, id));
+					}
+					Err(_) => {
+						T::Currency::unreserve(&owner, deposit);
+						break
+					}
+				}
+			}
+
+			reads += 1;
+			let cursor = match entries.peek() {
+				// The whole nursery is scanned, start over in the next interval.
+				None => None,
+				Some((next_owner, _, _))
+					if current_owner.as_ref() == Some(next_owner) && owner_start_key != start_key =>
+					owner_start_key,
+				Some(_) => last_key,
+			};
+			if let Some(key) = cursor {
+				NurseryCursor::<T>::put(key);
+			}
+			writes += stale.len() as Weight;
+			for (owner, id) in stale {
+				Nursery::<T>::remove(owner, id);
+			}
+			T::DbWeight::get().reads_writes(reads, writes)
 		}
 
 		fn ensure_owner(id: &T::KittyId, owner: &T::AccountId) -> DispatchResult {
@@ -316,6 +823,11 @@ pub mod pallet {
 			}
 		}
 
+		fn ensure_not_display_clone(id: &T::KittyId) -> DispatchResult {
+			ensure!(!DisplayClones::<T>::contains_key(id), Error::<T>::KittyIsDisplayClone);
+			Ok(())
+		}
+
 		fn transfer_kitty(
 			id: &T::KittyId,
 			owner: &T::AccountId,
@@ -325,6 +837,7 @@ pub mod pallet {
 
 			T::Currency::unreserve(&owner, T::HoldingDepositForOneKitty::get());
 			KittiesOwner::<T>::insert(id, new_owner.clone());
+			Nursery::<T>::remove(owner, id);
 
 			Ok(())
 		}
@@ -1,5 +1,6 @@
 use crate as pallet_kitties;
-use frame_support::parameter_types;
+use frame_support::{parameter_types, traits::Get};
+use std::cell::RefCell;
 use frame_system as system;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{self, H256};
@@ -84,6 +85,16 @@ parameter_types! {
 	pub const SS58Prefix: u8 = 42;
 	/// const for pallet_kitties
 	pub const HoldingDepositForOneKitty: Balance = 10_000;
+	pub const MaxRegistryExportBatch: u32 = 2;
+	pub const MaxDisplayClonesPerKitty: u32 = 2;
+	pub const NurseryInterval: u64 = 10;
+	pub const MaxNurseryBreedsPerInterval: u32 = 1;
+	pub const MaxNurseryScanPerInterval: u32 = 4;
+	pub const MinBreedingAge: u64 = 1_000;
+	pub const BreedingCooldown: u64 = 5_000;
+	pub const MaxBreedingTimes: u32 = 2;
+	pub const BreedingSeasonPeriod: u64 = 100_000;
+	pub const BreedingSeasonLength: u64 = 50_000;
 }
 
 impl system::Config for Test {
@@ -112,6 +123,22 @@ impl system::Config for Test {
 	type OnSetCode = ();
 }
 
+thread_local! {
+	static STRICT_BREEDING: RefCell<bool> = RefCell::new(false);
+}
+
+/// Strict breeding is disabled by default, tests can turn it on with `set_strict_breeding`.
+pub struct StrictBreeding;
+impl Get<bool> for StrictBreeding {
+	fn get() -> bool {
+		STRICT_BREEDING.with(|v| *v.borrow())
+	}
+}
+
+pub fn set_strict_breeding(strict: bool) {
+	STRICT_BREEDING.with(|v| *v.borrow_mut() = strict);
+}
+
 impl pallet_kitties::Config for Test {
 	type Event = Event;
 	type Randomness = RandomnessCollectiveFlip;
@@ -119,6 +146,18 @@ impl pallet_kitties::Config for Test {
 	type Currency = Balances;
 	type HoldingDepositForOneKitty = HoldingDepositForOneKitty;
 	type Time = Timestamp;
+	type AdminOrigin = frame_system::EnsureRoot<u64>;
+	type MaxRegistryExportBatch = MaxRegistryExportBatch;
+	type MaxDisplayClonesPerKitty = MaxDisplayClonesPerKitty;
+	type NurseryInterval = NurseryInterval;
+	type MaxNurseryBreedsPerInterval = MaxNurseryBreedsPerInterval;
+	type MaxNurseryScanPerInterval = MaxNurseryScanPerInterval;
+	type StrictBreeding = StrictBreeding;
+	type MinBreedingAge = MinBreedingAge;
+	type BreedingCooldown = BreedingCooldown;
+	type MaxBreedingTimes = MaxBreedingTimes;
+	type BreedingSeasonPeriod = BreedingSeasonPeriod;
+	type BreedingSeasonLength = BreedingSeasonLength;
 }
 
 pub fn new_test_ext() -> sp_io::TestExternalities {
@@ -1,5 +1,9 @@
-use crate::{mock::*, Error};
-use frame_support::{assert_noop, assert_ok};
+use crate::{mock::*, Error, Gender};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok, storage::unhashed, traits::Hooks, Blake2_128Concat, StorageHasher,
+};
+use sp_runtime::traits::BadOrigin;
 
 #[test]
 fn create_works() {
@@ -158,3 +162,491 @@ fn buy_works() {
 		assert_eq!(Kitties::kitties_price(1), Option::None);
 	});
 }
+
+#[test]
+fn corrupted_kitty_is_skipped_and_can_be_repaired() {
+	new_test_ext().execute_with(|| {
+		let mut block_number = 1;
+		System::set_block_number(block_number);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		block_number += 1;
+		System::set_block_number(block_number);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::adopt(Origin::signed(1), 1));
+		assert_ok!(Kitties::adopt(Origin::signed(1), 2));
+
+		// Simulate an entry left undecodable by a migration.
+		unhashed::put_raw(&crate::Kitties::<Test>::hashed_key_for(2), &[1u8, 2, 3]);
+		let ids = |list: Vec<(u32, crate::Kitty<Test>)>| {
+			list.into_iter().map(|(id, _)| id).collect::<Vec<_>>()
+		};
+		assert_eq!(ids(Kitties::all_kitties()), vec![1]);
+		assert_eq!(ids(Kitties::kitties_of(&1)), vec![1]);
+		assert_noop!(Kitties::breed(Origin::signed(1), 1, 2), Error::<Test>::KittyCorrupted);
+		assert_noop!(Kitties::breed(Origin::signed(1), 2, 1), Error::<Test>::KittyCorrupted);
+
+		assert_noop!(Kitties::repair_kitty(Origin::signed(1), 2, [1u8; 16]), BadOrigin);
+		assert_noop!(
+			Kitties::repair_kitty(Origin::root(), 1, [1u8; 16]),
+			Error::<Test>::KittyNotCorrupted
+		);
+		assert_noop!(
+			Kitties::repair_kitty(Origin::root(), 3, [1u8; 16]),
+			Error::<Test>::KittyNotExists
+		);
+		assert_ok!(Kitties::repair_kitty(Origin::root(), 2, [1u8; 16]));
+		System::assert_last_event(Event::Kitties(crate::Event::KittyRepaired(2)));
+		assert_eq!(ids(Kitties::all_kitties()), vec![1, 2]);
+		assert_eq!(ids(Kitties::kitties_of(&1)), vec![1, 2]);
+		assert_eq!(Kitties::kitties(2).unwrap().dna, [1u8; 16]);
+	});
+}
+
+#[test]
+fn export_registry_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		for _ in 0..3 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+		}
+		assert_ok!(Kitties::adopt(Origin::signed(2), 1));
+		assert_ok!(Kitties::set_price(Origin::signed(2), 1, 200));
+		assert_ok!(Kitties::adopt(Origin::signed(3), 3));
+		assert_ok!(Kitties::create_display(Origin::signed(2), 1));
+
+		assert_noop!(Kitties::export_registry(Origin::signed(1), 0, 10), BadOrigin);
+		assert_noop!(
+			Kitties::export_registry(Origin::root(), 0, 0),
+			Error::<Test>::ExportLimitIsZero
+		);
+
+		let entry = |id: u32| {
+			Event::Kitties(crate::Event::RegistryEntry(
+				id,
+				Kitties::kitties_owner(id),
+				Kitties::kitties(id).unwrap().dna,
+				Kitties::kitties_price(id),
+			))
+		};
+		let kitties_events = || {
+			System::events()
+				.into_iter()
+				.map(|record| record.event)
+				.filter(|event| matches!(event, Event::Kitties(_)))
+				.collect::<Vec<_>>()
+		};
+
+		// The page size is bounded by `MaxRegistryExportBatch`.
+		System::reset_events();
+		assert_ok!(Kitties::export_registry(Origin::root(), 0, 10));
+		assert_eq!(
+			kitties_events(),
+			vec![entry(1), entry(2), Event::Kitties(crate::Event::RegistryExported(Some(3)))]
+		);
+
+		// The display clone is exported with its source kitty.
+		let display_clone = Event::Kitties(crate::Event::RegistryDisplayClone(4, 1));
+		System::reset_events();
+		assert_ok!(Kitties::export_registry(Origin::root(), 3, 10));
+		assert_eq!(
+			kitties_events(),
+			vec![
+				entry(3),
+				entry(4),
+				display_clone.clone(),
+				Event::Kitties(crate::Event::RegistryExported(None)),
+			]
+		);
+
+		System::reset_events();
+		assert_ok!(Kitties::export_registry(Origin::root(), 5, 10));
+		assert_eq!(kitties_events(), vec![Event::Kitties(crate::Event::RegistryExported(None))]);
+
+		// An undecodable kitty is exported as corrupted instead of being dropped.
+		unhashed::put_raw(&crate::Kitties::<Test>::hashed_key_for(3), &[1u8, 2, 3]);
+		System::reset_events();
+		assert_ok!(Kitties::export_registry(Origin::root(), 3, 10));
+		assert_eq!(
+			kitties_events(),
+			vec![
+				Event::Kitties(crate::Event::RegistryEntryCorrupted(3, Some(3))),
+				entry(4),
+				display_clone,
+				Event::Kitties(crate::Event::RegistryExported(None)),
+			]
+		);
+	});
+}
+
+#[test]
+fn display_clone_works() {
+	new_test_ext().execute_with(|| {
+		let mut block_number = 1;
+		System::set_block_number(block_number);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		block_number += 1;
+		System::set_block_number(block_number);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::adopt(Origin::signed(1), 1));
+
+		assert_noop!(Kitties::create_display(Origin::signed(1), 3), Error::<Test>::KittyNotExists);
+		assert_noop!(Kitties::create_display(Origin::signed(2), 1), Error::<Test>::NotOwnerOfKitty);
+		let balance_before_create = Balances::free_balance(1);
+		assert_ok!(Kitties::create_display(Origin::signed(1), 1));
+		System::assert_last_event(Event::Kitties(crate::Event::DisplayCloneCreated(1, 3)));
+		assert_eq!(Balances::free_balance(1), balance_before_create);
+		assert_eq!(Kitties::display_clone_source(3), Some(1));
+		assert_eq!(Kitties::kitties(3).unwrap().dna, Kitties::kitties(1).unwrap().dna);
+		assert_eq!(Kitties::kitties_owner(3), Option::None);
+		assert_eq!(Kitties::kitties_count(), Some(2));
+		assert_eq!(Kitties::last_kitty_id(), Some(3));
+		assert_eq!(Kitties::display_clones_count(), Some(1));
+		let ids = Kitties::all_kitties().into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+		assert_eq!(ids, vec![1, 2]);
+
+		assert_noop!(Kitties::adopt(Origin::signed(1), 3), Error::<Test>::KittyIsDisplayClone);
+		assert_noop!(
+			Kitties::transfer(Origin::signed(1), 3, 2),
+			Error::<Test>::KittyIsDisplayClone
+		);
+		assert_noop!(Kitties::breed(Origin::signed(1), 2, 3), Error::<Test>::KittyIsDisplayClone);
+		assert_noop!(
+			Kitties::set_price(Origin::signed(1), 3, 200),
+			Error::<Test>::KittyIsDisplayClone
+		);
+		assert_noop!(Kitties::buy(Origin::signed(2), 3), Error::<Test>::KittyIsDisplayClone);
+		assert_noop!(
+			Kitties::repair_kitty(Origin::root(), 3, [1u8; 16]),
+			Error::<Test>::KittyIsDisplayClone
+		);
+		assert_noop!(
+			Kitties::create_display(Origin::signed(1), 3),
+			Error::<Test>::KittyIsDisplayClone
+		);
+
+		// A kitty has at most `MaxDisplayClonesPerKitty` display clones.
+		assert_ok!(Kitties::create_display(Origin::signed(1), 1));
+		assert_eq!(Kitties::display_clones_of(1), Some(2));
+		assert_noop!(
+			Kitties::create_display(Origin::signed(1), 1),
+			Error::<Test>::TooManyDisplayClones
+		);
+	});
+}
+
+/// Accounts 1 to 5 in the order their nursery entries are stored, as the owner is the first key.
+fn accounts_in_nursery_order() -> Vec<u64> {
+	let mut accounts = (1..=5).collect::<Vec<u64>>();
+	accounts.sort_by_key(|account| Blake2_128Concat::hash(&account.encode()));
+	accounts
+}
+
+/// Create kitties for account 1 until there are at least `count` kitties of each gender.
+fn create_kitties_of_both_genders(count: usize) -> (Vec<u32>, Vec<u32>) {
+	let mut males = vec![];
+	let mut females = vec![];
+	let mut block_number = System::block_number();
+	while males.len() < count || females.len() < count {
+		block_number += 1;
+		System::set_block_number(block_number);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		let id = Kitties::kitties_count().unwrap();
+		match Kitties::kitties(id).unwrap().gender() {
+			Gender::Male => males.push(id),
+			Gender::Female => females.push(id),
+		}
+	}
+	(males, females)
+}
+
+#[test]
+fn deposit_and_withdraw_nursery_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::adopt(Origin::signed(1), 1));
+
+		assert_noop!(
+			Kitties::deposit_to_nursery(Origin::signed(1), 2),
+			Error::<Test>::KittyNotExists
+		);
+		assert_noop!(
+			Kitties::deposit_to_nursery(Origin::signed(2), 1),
+			Error::<Test>::NotOwnerOfKitty
+		);
+		assert_ok!(Kitties::deposit_to_nursery(Origin::signed(1), 1));
+		System::assert_last_event(Event::Kitties(crate::Event::KittyDepositedToNursery(1, 1)));
+		assert_eq!(Kitties::nursery(1, 1), Some(()));
+		assert_noop!(
+			Kitties::deposit_to_nursery(Origin::signed(1), 1),
+			Error::<Test>::KittyAlreadyInNursery
+		);
+
+		assert_noop!(
+			Kitties::withdraw_from_nursery(Origin::signed(2), 1),
+			Error::<Test>::KittyNotInNursery
+		);
+		assert_ok!(Kitties::withdraw_from_nursery(Origin::signed(1), 1));
+		System::assert_last_event(Event::Kitties(crate::Event::KittyWithdrawnFromNursery(1, 1)));
+		assert_eq!(Kitties::nursery(1, 1), Option::None);
+	});
+}
+
+#[test]
+fn nursery_breeding_works() {
+	new_test_ext().execute_with(|| {
+		let (males, females) = create_kitties_of_both_genders(2);
+		let parents = [&males[..2], &females[..2]].concat();
+		for id in parents.iter() {
+			assert_ok!(Kitties::adopt(Origin::signed(1), *id));
+			assert_ok!(Kitties::deposit_to_nursery(Origin::signed(1), *id));
+		}
+
+		let interval_block = (System::block_number() / 10 + 1) * 10;
+		let count_before_breeding = Kitties::kitties_count().unwrap();
+		Kitties::on_initialize(interval_block - 1);
+		assert_eq!(Kitties::kitties_count(), Some(count_before_breeding));
+
+		// Only `MaxNurseryBreedsPerInterval` kitties are born in one interval.
+		let reserved_before_breeding = Balances::reserved_balance(1);
+		Kitties::on_initialize(interval_block);
+		let child = Kitties::kitties_count().unwrap();
+		assert_eq!(child, count_before_breeding + 1);
+		assert_eq!(Kitties::kitties_owner(child), Some(1));
+		assert_eq!(Balances::reserved_balance(1) - reserved_before_breeding, 10_000);
+		match System::events().pop().unwrap().event {
+			Event::Kitties(crate::Event::NurseryBreeding(owner, id, parent1, parent2)) => {
+				assert_eq!((owner, id), (1, child));
+				assert!(parents.contains(&parent1) && parents.contains(&parent2));
+				assert_ne!(
+					Kitties::kitties(parent1).unwrap().gender(),
+					Kitties::kitties(parent2).unwrap().gender()
+				);
+			}
+			event => panic!("unexpected event: {:?}", event),
+		}
+	});
+}
+
+#[test]
+fn nursery_scan_is_bounded() {
+	new_test_ext().execute_with(|| {
+		// Three male kitties of the first owner and a single kitty for each of the next three,
+		// so no pair can be formed and the scan limit does not split an owner.
+		let owners = accounts_in_nursery_order();
+		let (males, _) = create_kitties_of_both_genders(3);
+		for id in males[..3].iter() {
+			assert_ok!(Kitties::adopt(Origin::signed(owners[0]), *id));
+			assert_ok!(Kitties::deposit_to_nursery(Origin::signed(owners[0]), *id));
+		}
+		for owner in owners[1..4].iter() {
+			assert_ok!(Kitties::create(Origin::signed(*owner)));
+			let id = Kitties::last_kitty_id().unwrap();
+			assert_ok!(Kitties::adopt(Origin::signed(*owner), id));
+			assert_ok!(Kitties::deposit_to_nursery(Origin::signed(*owner), id));
+		}
+		let entries =
+			crate::Nursery::<Test>::iter().map(|(owner, id, _)| (owner, id)).collect::<Vec<_>>();
+		assert_eq!(entries.len(), 6);
+
+		// Only `MaxNurseryScanPerInterval` entries are visited in one interval.
+		let interval_block = (System::block_number() / 10 + 1) * 10;
+		let count_before_breeding = Kitties::kitties_count();
+		Kitties::on_initialize(interval_block);
+		assert_eq!(
+			crate::NurseryCursor::<Test>::get(),
+			Some(crate::Nursery::<Test>::hashed_key_for(entries[3].0, entries[3].1))
+		);
+
+		// The next interval resumes from the cursor and starts over after the last entry.
+		Kitties::on_initialize(interval_block + 10);
+		assert_eq!(crate::NurseryCursor::<Test>::get(), Option::None);
+		assert_eq!(Kitties::kitties_count(), count_before_breeding);
+	});
+}
+
+#[test]
+fn nursery_pairs_kitties_split_by_scan_limit() {
+	new_test_ext().execute_with(|| {
+		// Three male kitties of the first owner are followed by the pair of the last owner,
+		// so the scan limit of 4 falls between the two kitties of the pair.
+		let owners = accounts_in_nursery_order();
+		let (males, females) = create_kitties_of_both_genders(4);
+		for id in males[..3].iter() {
+			assert_ok!(Kitties::adopt(Origin::signed(owners[0]), *id));
+			assert_ok!(Kitties::deposit_to_nursery(Origin::signed(owners[0]), *id));
+		}
+		for id in [males[3], females[0]].iter() {
+			assert_ok!(Kitties::adopt(Origin::signed(owners[4]), *id));
+			assert_ok!(Kitties::deposit_to_nursery(Origin::signed(owners[4]), *id));
+		}
+
+		let interval_block = (System::block_number() / 10 + 1) * 10;
+		let count_before_breeding = Kitties::kitties_count().unwrap();
+		Kitties::on_initialize(interval_block);
+		assert_eq!(Kitties::kitties_count(), Some(count_before_breeding));
+
+		// The next interval resumes from the first kitty of the pair.
+		Kitties::on_initialize(interval_block + 10);
+		assert_eq!(Kitties::kitties_count(), Some(count_before_breeding + 1));
+		let child = Kitties::last_kitty_id().unwrap();
+		assert_eq!(Kitties::kitties_owner(child), Some(owners[4]));
+	});
+}
+
+#[test]
+fn nursery_entry_is_removed_when_kitty_changes_hands() {
+	new_test_ext().execute_with(|| {
+		let (males, females) = create_kitties_of_both_genders(1);
+		let (male, female) = (males[0], females[0]);
+		for id in [male, female].iter() {
+			assert_ok!(Kitties::adopt(Origin::signed(1), *id));
+			assert_ok!(Kitties::deposit_to_nursery(Origin::signed(1), *id));
+		}
+
+		// A -> B -> A must not bring the kitty back into A's nursery.
+		assert_ok!(Kitties::transfer(Origin::signed(1), male, 2));
+		assert_eq!(Kitties::nursery(1, male), Option::None);
+		assert_ok!(Kitties::transfer(Origin::signed(2), male, 1));
+		assert_eq!(Kitties::nursery(1, male), Option::None);
+
+		// The female is still in the nursery, but the male is not paired with it.
+		let interval_block = (System::block_number() / 10 + 1) * 10;
+		let count_before_breeding = Kitties::kitties_count();
+		Kitties::on_initialize(interval_block);
+		assert_eq!(Kitties::kitties_count(), count_before_breeding);
+
+		assert_ok!(Kitties::abandon(Origin::signed(1), female));
+		assert_eq!(Kitties::nursery(1, female), Option::None);
+	});
+}
+
+#[test]
+fn strict_breeding_follows_error_precedence() {
+	new_test_ext().execute_with(|| {
+		let (males, females) = create_kitties_of_both_genders(3);
+		let (male1, male2, ownerless_male, female) = (males[0], males[1], males[2], females[0]);
+		for id in [male1, male2, female].iter() {
+			assert_ok!(Kitties::adopt(Origin::signed(1), *id));
+		}
+		assert_ok!(Kitties::create_display(Origin::signed(1), male1));
+		let display = Kitties::last_kitty_id().unwrap();
+
+		// Without strict mode, only existence, display clone and gender are checked.
+		assert_noop!(
+			Kitties::breed(Origin::signed(1), male1, male1),
+			Error::<Test>::CanNotBreedWithSameGender
+		);
+		assert_ok!(Kitties::breed(Origin::signed(1), ownerless_male, female));
+
+		set_strict_breeding(true);
+		// Not exists & self & ownerless.
+		assert_noop!(Kitties::breed(Origin::signed(1), 999, 999), Error::<Test>::KittyNotExists);
+		// Self & ownerless & same gender.
+		assert_noop!(
+			Kitties::breed(Origin::signed(1), ownerless_male, ownerless_male),
+			Error::<Test>::CanNotBreedWithSelf
+		);
+		// Display clone & ownerless & same gender.
+		assert_noop!(
+			Kitties::breed(Origin::signed(1), ownerless_male, display),
+			Error::<Test>::KittyIsDisplayClone
+		);
+		// Ownerless & same gender.
+		assert_noop!(
+			Kitties::breed(Origin::signed(1), male1, ownerless_male),
+			Error::<Test>::CanNotBreedOwnerlessKitty
+		);
+		assert_noop!(
+			Kitties::breed(Origin::signed(1), ownerless_male, female),
+			Error::<Test>::CanNotBreedOwnerlessKitty
+		);
+		// Too young & same gender, all kitties are born at moment 0.
+		assert_noop!(Kitties::breed(Origin::signed(1), male1, male2), Error::<Test>::KittyTooYoung);
+
+		Timestamp::set_timestamp(4_000);
+		// In cooldown, the female bred at moment 0.
+		assert_noop!(
+			Kitties::breed(Origin::signed(1), male1, female),
+			Error::<Test>::KittyInCooldown
+		);
+		// Same gender only.
+		assert_noop!(
+			Kitties::breed(Origin::signed(1), male1, male2),
+			Error::<Test>::CanNotBreedWithSameGender
+		);
+
+		Timestamp::set_timestamp(5_000);
+		assert_ok!(Kitties::breed(Origin::signed(1), male1, female));
+		assert_eq!(Kitties::kitties_last_bred(male1), Some(5_000));
+		// Too young & in cooldown (& maybe same gender).
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		let young = Kitties::last_kitty_id().unwrap();
+		assert_ok!(Kitties::adopt(Origin::signed(1), young));
+		assert_noop!(
+			Kitties::breed(Origin::signed(1), male1, young),
+			Error::<Test>::KittyTooYoung
+		);
+		// In cooldown & same gender.
+		assert_noop!(
+			Kitties::breed(Origin::signed(1), male1, male2),
+			Error::<Test>::KittyInCooldown
+		);
+
+		Timestamp::set_timestamp(10_000);
+		assert_noop!(
+			Kitties::breed(Origin::signed(1), male1, male2),
+			Error::<Test>::CanNotBreedWithSameGender
+		);
+		// Sterile, the female became a parent twice.
+		assert_eq!(Kitties::kitties_bred_times(female), Some(2));
+		assert_noop!(Kitties::breed(Origin::signed(1), male2, female), Error::<Test>::KittySterile);
+
+		// Out of breeding season & sterile.
+		Timestamp::set_timestamp(60_000);
+		assert_noop!(
+			Kitties::breed(Origin::signed(1), male2, female),
+			Error::<Test>::OutOfBreedingSeason
+		);
+	});
+}
+
+#[test]
+fn nursery_breeding_follows_strict_checks() {
+	new_test_ext().execute_with(|| {
+		set_strict_breeding(true);
+		let (males, females) = create_kitties_of_both_genders(1);
+		for id in [males[0], females[0]].iter() {
+			assert_ok!(Kitties::adopt(Origin::signed(1), *id));
+			assert_ok!(Kitties::deposit_to_nursery(Origin::signed(1), *id));
+		}
+		let mut interval_block = (System::block_number() / 10 + 1) * 10;
+		let count_before_breeding = Kitties::kitties_count().unwrap();
+
+		// Too young, all kitties are born at moment 0.
+		Kitties::on_initialize(interval_block);
+		assert_eq!(Kitties::kitties_count(), Some(count_before_breeding));
+
+		Timestamp::set_timestamp(2_000);
+		interval_block += 10;
+		Kitties::on_initialize(interval_block);
+		assert_eq!(Kitties::kitties_count(), Some(count_before_breeding + 1));
+
+		// In cooldown.
+		Timestamp::set_timestamp(3_000);
+		interval_block += 10;
+		Kitties::on_initialize(interval_block);
+		assert_eq!(Kitties::kitties_count(), Some(count_before_breeding + 1));
+
+		Timestamp::set_timestamp(7_000);
+		interval_block += 10;
+		Kitties::on_initialize(interval_block);
+		assert_eq!(Kitties::kitties_count(), Some(count_before_breeding + 2));
+
+		// Sterile after becoming a parent twice.
+		Timestamp::set_timestamp(20_000);
+		interval_block += 10;
+		Kitties::on_initialize(interval_block);
+		assert_eq!(Kitties::kitties_count(), Some(count_before_breeding + 2));
+	});
+}
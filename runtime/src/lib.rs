@@ -142,6 +142,19 @@ parameter_types! {
 	pub const SS58Prefix: u8 = 42;
 	/// const for pallet_kitties
 	pub const HoldingDepositForOneKitty: Balance = 10_000_000_000_000;
+	pub const MaxRegistryExportBatch: u32 = 100;
+	pub const MaxDisplayClonesPerKitty: u32 = 10;
+	pub const NurseryInterval: BlockNumber = 100;
+	pub const MaxNurseryBreedsPerInterval: u32 = 10;
+	pub const MaxNurseryScanPerInterval: u32 = 100;
+	pub const StrictBreeding: bool = false;
+	/// One day, in milliseconds.
+	pub const MinBreedingAge: u64 = 24 * 60 * 60 * 1000;
+	/// One hour, in milliseconds.
+	pub const BreedingCooldown: u64 = 60 * 60 * 1000;
+	pub const MaxBreedingTimes: u32 = 10;
+	pub const BreedingSeasonPeriod: u64 = 0;
+	pub const BreedingSeasonLength: u64 = 0;
 }
 
 // Configure FRAME pallets to include in runtime.
@@ -281,6 +294,18 @@ impl pallet_kitties::Config for Runtime {
 	type Currency = Balances;
 	type HoldingDepositForOneKitty = HoldingDepositForOneKitty;
 	type Time = Timestamp;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxRegistryExportBatch = MaxRegistryExportBatch;
+	type MaxDisplayClonesPerKitty = MaxDisplayClonesPerKitty;
+	type NurseryInterval = NurseryInterval;
+	type MaxNurseryBreedsPerInterval = MaxNurseryBreedsPerInterval;
+	type MaxNurseryScanPerInterval = MaxNurseryScanPerInterval;
+	type StrictBreeding = StrictBreeding;
+	type MinBreedingAge = MinBreedingAge;
+	type BreedingCooldown = BreedingCooldown;
+	type MaxBreedingTimes = MaxBreedingTimes;
+	type BreedingSeasonPeriod = BreedingSeasonPeriod;
+	type BreedingSeasonLength = BreedingSeasonLength;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.